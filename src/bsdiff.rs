@@ -0,0 +1,296 @@
+// A bsdiff-style binary delta engine.
+//
+// The old file is indexed with a suffix array so that, for every position in
+// the new file, we can locate the longest run of bytes in the old file that
+// approximately matches what comes next. Runs of matched bytes are encoded
+// as a control triple plus a byte-wise diff against the old file; runs that
+// don't match anything are encoded as literal "extra" bytes. This mirrors
+// the control/diff/extra stream layout used by bspatch.
+
+// Matches shorter than this are not worth encoding as a copy; the bytes are
+// emitted as extra data instead.
+const MIN_MATCH_LEN: usize = 8;
+
+/// One entry in the control stream: copy `copy_len` bytes from the old file
+/// (diffed against the diff stream), then append `extra_len` literal bytes
+/// from the extra stream, then seek the old file cursor by `old_seek`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ControlEntry {
+    pub copy_len: u32,
+    pub extra_len: u32,
+    pub old_seek: i64,
+}
+
+/// The three streams a bsdiff-style patch is made of.
+#[derive(Clone, Debug, Default)]
+pub struct Delta {
+    pub controls: Vec<ControlEntry>,
+    pub diff_bytes: Vec<u8>,
+    pub extra_bytes: Vec<u8>,
+}
+
+/// Build a suffix array over `data`: the indices `0..data.len()`, sorted by
+/// the lexicographic order of the suffix starting at each index.
+///
+/// Uses prefix doubling: each round sorts suffixes by a pair of ranks
+/// (the rank after 2^(round-1) bytes, twice), so comparisons are O(1)
+/// regardless of how much of the data repeats. A naive comparator that
+/// re-compares whole suffixes byte-by-byte degrades to O(n) per comparison
+/// on long repeated runs (e.g. padding in console binaries), which made
+/// construction effectively O(n^2 log n).
+fn build_suffix_array(data: &[u8]) -> Vec<usize> {
+    let n = data.len();
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let mut sa: Vec<usize> = (0..n).collect();
+    let mut rank: Vec<i64> = data.iter().map(|&b| b as i64).collect();
+    let mut next_rank = vec![0i64; n];
+    let mut k = 1usize;
+
+    loop {
+        let key = |i: usize| -> (i64, i64) {
+            let second = if i + k < n { rank[i + k] } else { -1 };
+            (rank[i], second)
+        };
+
+        sa.sort_unstable_by_key(|&i| key(i));
+
+        next_rank[sa[0]] = 0;
+        for idx in 1..n {
+            let bump = if key(sa[idx - 1]) < key(sa[idx]) { 1 } else { 0 };
+            next_rank[sa[idx]] = next_rank[sa[idx - 1]] + bump;
+        }
+        rank.copy_from_slice(&next_rank);
+
+        if rank[sa[n - 1]] as usize == n - 1 || k >= n {
+            break;
+        }
+        k *= 2;
+    }
+
+    sa
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count()
+}
+
+/// Binary search the suffix array for the suffix with the longest common
+/// prefix with `needle`. Returns `(match_len, old_pos)`.
+fn longest_exact_match(old: &[u8], suffix_array: &[usize], needle: &[u8]) -> (usize, usize) {
+    if needle.is_empty() || suffix_array.is_empty() {
+        return (0, 0);
+    }
+
+    let mut lo = 0usize;
+    let mut hi = suffix_array.len();
+    let mut best_len = 0usize;
+    let mut best_pos = suffix_array[0];
+
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let candidate = suffix_array[mid];
+        let suffix = &old[candidate..];
+        let prefix_len = common_prefix_len(needle, suffix);
+        if prefix_len > best_len {
+            best_len = prefix_len;
+            best_pos = candidate;
+        }
+        if suffix.len() == prefix_len || needle[prefix_len..].is_empty() {
+            break;
+        }
+        if needle[prefix_len] < suffix[prefix_len] {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+
+    (best_len, best_pos)
+}
+
+/// Extend an exact match approximately: keep growing it past mismatches as
+/// long as at least half of the extended bytes still agree.
+fn extend_match(old: &[u8], new: &[u8], old_pos: usize, new_pos: usize, exact_len: usize) -> usize {
+    let mut len = exact_len;
+    let mut extra_total = 0usize;
+    let mut extra_agree = 0usize;
+
+    loop {
+        if old_pos + len >= old.len() || new_pos + len >= new.len() {
+            break;
+        }
+        extra_total += 1;
+        if old[old_pos + len] == new[new_pos + len] {
+            extra_agree += 1;
+        }
+        if extra_agree * 2 < extra_total {
+            break;
+        }
+        len += 1;
+    }
+
+    len
+}
+
+/// Find the longest approximate match in `old` for the bytes starting at
+/// `new[new_pos..]`. Returns `(match_len, old_pos)`.
+fn find_longest_match(
+    old: &[u8],
+    suffix_array: &[usize],
+    new: &[u8],
+    new_pos: usize,
+) -> (usize, usize) {
+    let (exact_len, old_pos) = longest_exact_match(old, suffix_array, &new[new_pos..]);
+    if exact_len == 0 {
+        return (0, old_pos);
+    }
+    let len = extend_match(old, new, old_pos, new_pos, exact_len);
+    (len, old_pos)
+}
+
+/// Compute a bsdiff-style delta that turns `old` into `new`.
+pub fn compute_delta(old: &[u8], new: &[u8]) -> Delta {
+    let suffix_array = build_suffix_array(old);
+    let mut delta = Delta::default();
+
+    let mut new_pos = 0usize;
+    let mut old_end: i64 = 0;
+
+    while new_pos < new.len() {
+        let (match_len, match_old_pos) = find_longest_match(old, &suffix_array, new, new_pos);
+        let copy_len = if match_len >= MIN_MATCH_LEN { match_len } else { 0 };
+
+        if copy_len > 0 {
+            // `apply_delta` starts copying wherever the old cursor stands, which
+            // is 0 before the first entry; a first match elsewhere in the old
+            // file needs an empty entry that only seeks to it.
+            if match_old_pos as i64 != old_end {
+                delta.controls.push(ControlEntry {
+                    copy_len: 0,
+                    extra_len: 0,
+                    old_seek: match_old_pos as i64 - old_end,
+                });
+            }
+            for k in 0..copy_len {
+                delta
+                    .diff_bytes
+                    .push(new[new_pos + k].wrapping_sub(old[match_old_pos + k]));
+            }
+            new_pos += copy_len;
+            old_end = (match_old_pos + copy_len) as i64;
+        }
+
+        // Accumulate literal bytes until the next usable match (or EOF).
+        let extra_start = new_pos;
+        let mut next_match: Option<usize> = None;
+        while new_pos < new.len() {
+            let (ml, mp) = find_longest_match(old, &suffix_array, new, new_pos);
+            if ml >= MIN_MATCH_LEN {
+                next_match = Some(mp);
+                break;
+            }
+            delta.extra_bytes.push(new[new_pos]);
+            new_pos += 1;
+        }
+        let extra_len = new_pos - extra_start;
+
+        let next_old_pos = next_match.map(|p| p as i64).unwrap_or(old_end);
+        let old_seek = next_old_pos - old_end;
+        old_end += old_seek;
+
+        delta.controls.push(ControlEntry {
+            copy_len: copy_len as u32,
+            extra_len: extra_len as u32,
+            old_seek,
+        });
+    }
+
+    delta
+}
+
+/// Apply a delta produced by [`compute_delta`] to `old`, reconstructing `new`.
+pub fn apply_delta(old: &[u8], delta: &Delta) -> Vec<u8> {
+    let mut output = Vec::new();
+    let mut old_pos: i64 = 0;
+    let mut diff_idx = 0usize;
+    let mut extra_idx = 0usize;
+
+    for entry in &delta.controls {
+        let copy_len = entry.copy_len as usize;
+        for k in 0..copy_len {
+            let old_byte = old[old_pos as usize + k];
+            let diff_byte = delta.diff_bytes[diff_idx];
+            diff_idx += 1;
+            output.push(old_byte.wrapping_add(diff_byte));
+        }
+        old_pos += copy_len as i64;
+
+        let extra_len = entry.extra_len as usize;
+        output.extend_from_slice(&delta.extra_bytes[extra_idx..extra_idx + extra_len]);
+        extra_idx += extra_len;
+
+        old_pos += entry.old_seek;
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(old: &[u8], new: &[u8]) {
+        let delta = compute_delta(old, new);
+        let reconstructed = apply_delta(old, &delta);
+        assert_eq!(reconstructed, new);
+    }
+
+    #[test]
+    fn round_trip_insertion() {
+        round_trip(b"AAAAABBBBBCCCCC", b"AAAAABBXXBBBCCCCC");
+    }
+
+    #[test]
+    fn round_trip_deletion() {
+        round_trip(b"AAAAABBBBBCCCCC", b"AAAAABBCCCCC");
+    }
+
+    #[test]
+    fn round_trip_shifted_alignment() {
+        // Prepending bytes shifts every later byte's offset, the case a
+        // naive line-oriented diff can't handle but bsdiff-style matching
+        // should recover via the suffix array.
+        round_trip(b"BBBBBCCCCCDDDDD", b"AAAAABBBBBCCCCCDDDDD");
+    }
+
+    #[test]
+    fn round_trip_repeated_bytes() {
+        let old = vec![0u8; 5000];
+        let mut new = vec![0u8; 4000];
+        new.extend(std::iter::repeat_n(1u8, 2000));
+        round_trip(&old, &new);
+    }
+
+    #[test]
+    fn round_trip_empty_old_file() {
+        round_trip(b"", b"freshly created content");
+    }
+
+    #[test]
+    fn round_trip_empty_new_file() {
+        round_trip(b"some prior content", b"");
+    }
+
+    #[test]
+    fn round_trip_both_empty() {
+        round_trip(b"", b"");
+    }
+
+    #[test]
+    fn round_trip_identical_files() {
+        round_trip(b"no changes at all", b"no changes at all");
+    }
+}
@@ -0,0 +1,116 @@
+// Pluggable compression codecs for the outer patch container.
+//
+// `compress_diff`/`decompress_diff` used to assume gzip. Now the container
+// starts with a small self-describing header (magic + format version +
+// codec tag) so `apply` can pick the right decoder at runtime instead of
+// assuming one, the same way bspatch separates patch framing from payload
+// compression.
+
+use std::io::{self, Read, Write};
+
+use bzip2::read::BzDecoder;
+use bzip2::write::BzEncoder;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+/// Identifies an oxidiff patch container.
+pub const MAGIC: &[u8; 4] = b"OXDF";
+
+/// Current patch container format version.
+///
+/// Bumped to 2 when the header gained a payload-length field, so
+/// `open_patch_stream` can bound the decoder to the compressed payload
+/// instead of handing it the rest of the file.
+pub const FORMAT_VERSION: u8 = 2;
+
+/// The compression codec applied to a patch container's payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Codec {
+    Gzip,
+    Bzip2,
+    Zstd,
+    None,
+}
+
+impl Codec {
+    /// The single-byte tag stored in the patch header for this codec.
+    pub fn tag(&self) -> u8 {
+        match self {
+            Codec::Gzip => 0,
+            Codec::Bzip2 => 1,
+            Codec::Zstd => 2,
+            Codec::None => 3,
+        }
+    }
+
+    /// Resolve a codec from a header tag byte.
+    pub fn from_tag(tag: u8) -> io::Result<Self> {
+        match tag {
+            0 => Ok(Codec::Gzip),
+            1 => Ok(Codec::Bzip2),
+            2 => Ok(Codec::Zstd),
+            3 => Ok(Codec::None),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown codec tag {}", other),
+            )),
+        }
+    }
+
+    /// Resolve a codec from a `--codec` CLI flag value.
+    pub fn from_flag(flag: &str) -> Option<Self> {
+        match flag {
+            "gzip" => Some(Codec::Gzip),
+            "bzip2" => Some(Codec::Bzip2),
+            "zstd" => Some(Codec::Zstd),
+            "none" => Some(Codec::None),
+            _ => None,
+        }
+    }
+}
+
+/// Compress all of `reader` into `writer` using `codec`.
+pub fn compress_stream<R: Read, W: Write>(codec: Codec, reader: &mut R, writer: &mut W) -> io::Result<()> {
+    match codec {
+        Codec::Gzip => {
+            let mut encoder = GzEncoder::new(writer, Compression::default());
+            io::copy(reader, &mut encoder)?;
+            encoder.finish()?;
+        }
+        Codec::Bzip2 => {
+            let mut encoder = BzEncoder::new(writer, bzip2::Compression::default());
+            io::copy(reader, &mut encoder)?;
+            encoder.finish()?;
+        }
+        Codec::Zstd => {
+            let mut encoder = zstd::stream::Encoder::new(writer, 0)?;
+            io::copy(reader, &mut encoder)?;
+            encoder.finish()?;
+        }
+        Codec::None => {
+            io::copy(reader, writer)?;
+        }
+    }
+    Ok(())
+}
+
+/// Wrap `reader` in a decoder for `codec`, returning a `Read` that yields
+/// decompressed bytes incrementally.
+///
+/// None of these decoders stops reading exactly at the end of its own
+/// compressed stream: `GzDecoder`, `BzDecoder` and the zstd decoder all read
+/// ahead into an internal buffer, taking whatever comes after their
+/// compressed data on `reader`, and without `single_frame` the zstd decoder
+/// would also try to parse trailing bytes as a second frame. So `reader`
+/// must already be bounded to exactly the
+/// compressed payload (e.g. via `Read::take`) before it reaches this
+/// function if anything follows it in the underlying stream.
+pub fn decoding_reader<R: Read + 'static>(codec: Codec, reader: R) -> io::Result<Box<dyn Read>> {
+    Ok(match codec {
+        Codec::Gzip => Box::new(GzDecoder::new(reader)),
+        Codec::Bzip2 => Box::new(BzDecoder::new(reader)),
+        Codec::Zstd => Box::new(zstd::stream::Decoder::new(reader)?.single_frame()),
+        Codec::None => Box::new(reader),
+    })
+}
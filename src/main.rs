@@ -1,34 +1,20 @@
 use std::fs::File;
-use std::io::{self, BufReader, BufWriter, Read, Write, Seek, SeekFrom};
+use std::io::{self, BufReader, BufWriter, Cursor, Read, Write, Seek, SeekFrom};
 use std::path::Path;
-use flate2::Compression;
-use flate2::read::GzDecoder;
-use flate2::write::GzEncoder;
 use sha2::{Sha256, Digest};
 use byteorder::{ReadBytesExt, WriteBytesExt, LittleEndian};
 
-// Define the size of the buffer used for file operations (1 MB)
-const BUFFER_SIZE: usize = 1024 * 1024;
+mod bsdiff;
+mod codec;
+mod rdiff;
+mod yaz0;
 
-// Struct to represent a disassembled instruction
-#[derive(Clone, Debug)]
-struct Instruction {
-    op_code: String,
-    address: i64,
-    operands: String,
-}
-
-impl Instruction {
-    // Constructor for the Instruction struct
-    fn new(op_code: String, address: i64, operands: String) -> Self {
-        Self { op_code, address, operands }
-    }
+use bsdiff::{ControlEntry, Delta};
+use codec::Codec;
+use rdiff::{RdiffCommand, Signature};
 
-    // Convert the instruction to a string representation
-    fn to_string(&self) -> String {
-        format!("{} {:08X} {}", self.op_code, self.address, self.operands)
-    }
-}
+// Define the size of the buffer used for file operations (1 MB)
+const BUFFER_SIZE: usize = 1024 * 1024;
 
 // Calculate the SHA256 hash of a file, reading it in chunks
 fn calculate_file_hash<R: Read>(reader: &mut R) -> io::Result<[u8; 32]> {
@@ -44,187 +30,364 @@ fn calculate_file_hash<R: Read>(reader: &mut R) -> io::Result<[u8; 32]> {
     Ok(hasher.finalize().into())
 }
 
-// Disassemble a file into a vector of Instructions, reading in chunks
-fn streaming_disassemble<R: Read>(reader: &mut R) -> io::Result<Vec<Instruction>> {
-    let mut buffer = [0u8; BUFFER_SIZE];
-    let mut instructions = Vec::new();
-    let mut address = 0;
+// Compress a byte buffer in memory with `codec`, reusing the same encoders
+// `compress_diff` uses for the outer patch container.
+fn compress_bytes(codec: Codec, data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    codec::compress_stream(codec, &mut &data[..], &mut out)?;
+    Ok(out)
+}
 
-    loop {
-        let bytes_read = reader.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
-        }
+// Decompress a byte buffer in memory with `codec`. `Cursor` gives
+// `decoding_reader` an owned, 'static reader without copying `data` onto the
+// heap twice.
+fn decompress_bytes(codec: Codec, data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut reader = codec::decoding_reader(codec, Cursor::new(data.to_vec()))?;
+    let mut out = Vec::new();
+    reader.read_to_end(&mut out)?;
+    Ok(out)
+}
 
-        // Process each 4-byte chunk as an instruction
-        for chunk in buffer[..bytes_read].chunks(4) {
-            let op_code = format!("OP{:02X}", address);
-            let operands = format!("OPERAND{:02X}", address);
-            instructions.push(Instruction::new(op_code, address as i64, operands));
-            address += chunk.len() as i64;
-        }
-    }
+// Write a length-prefixed byte stream compressed with `codec`. Used by the
+// signature and rdiff patch formats, which (unlike `create`/`apply`) have no
+// outer container layer of their own to compress the whole file.
+fn write_compressed_stream<W: Write>(writer: &mut W, codec: Codec, data: &[u8]) -> io::Result<()> {
+    let compressed = compress_bytes(codec, data)?;
+    writer.write_u32::<LittleEndian>(compressed.len() as u32)?;
+    writer.write_all(&compressed)?;
+    Ok(())
+}
 
-    Ok(instructions)
+// Read a length-prefixed byte stream written by `write_compressed_stream`.
+fn read_compressed_stream<R: Read>(reader: &mut R, codec: Codec) -> io::Result<Vec<u8>> {
+    let len = reader.read_u32::<LittleEndian>()?;
+    let mut compressed = vec![0u8; len as usize];
+    reader.read_exact(&mut compressed)?;
+    decompress_bytes(codec, &compressed)
 }
 
-// Normalize the assembly code by replacing addresses with a placeholder
-fn normalize(assembly_code: &[Instruction]) -> Vec<Instruction> {
-    assembly_code.iter().map(|instruction| {
-        Instruction::new(
-            instruction.op_code.clone(),
-            0,
-            instruction.operands.replace(&format!("{:08X}", instruction.address), "SYMREF"),
-        )
-    }).collect()
+// Write a length-prefixed, *uncompressed* byte stream. The bsdiff control,
+// diff and extra streams use this instead of `write_compressed_stream`: the
+// outer patch container (`compress_diff`/`open_patch_stream`) already
+// compresses the whole diff body with the user-selected codec, so
+// compressing these individually too would double-compress the bytes and
+// make `--codec none` lie about producing a genuinely uncompressed patch.
+fn write_framed_stream<W: Write>(writer: &mut W, data: &[u8]) -> io::Result<()> {
+    writer.write_u32::<LittleEndian>(data.len() as u32)?;
+    writer.write_all(data)?;
+    Ok(())
 }
 
-// Generate a diff between old and new code
-fn generate_diff(old_code: &[Instruction], new_code: &[Instruction]) -> Vec<String> {
-    let mut diff = Vec::new();
-    let max_length = old_code.len().max(new_code.len());
-    for i in 0..max_length {
-        if i >= old_code.len() {
-            // New instruction added
-            diff.push(format!("+{}", new_code[i].to_string()));
-        } else if i >= new_code.len() {
-            // Old instruction removed
-            diff.push(format!("-{}", old_code[i].to_string()));
-        } else if old_code[i].to_string() != new_code[i].to_string() {
-            // Instruction changed
-            diff.push(format!("~{} -> {}", old_code[i].to_string(), new_code[i].to_string()));
-        }
-    }
-    diff
+// Read a length-prefixed byte stream written by `write_framed_stream`.
+fn read_framed_stream<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let len = reader.read_u32::<LittleEndian>()?;
+    let mut data = vec![0u8; len as usize];
+    reader.read_exact(&mut data)?;
+    Ok(data)
 }
 
-// Extract address changes between old and new disassembled code
-fn extract_address_changes(old_disassembled: &[Instruction], new_disassembled: &[Instruction]) -> Vec<i64> {
-    old_disassembled.iter().zip(new_disassembled.iter())
-        .map(|(old, new)| new.address - old.address)
-        .collect()
+// If `raw` is Yaz0-wrapped, transparently decode it so the rest of the
+// pipeline diffs the real payload instead of the container shell.
+fn decode_if_yaz0(raw: &[u8]) -> io::Result<(Vec<u8>, bool)> {
+    if yaz0::is_yaz0(raw) {
+        Ok((yaz0::decode(raw)?, true))
+    } else {
+        Ok((raw.to_vec(), false))
+    }
 }
 
-// Create a streaming diff between two files
+// Create a streaming diff between two files using the bsdiff engine. The
+// control, diff and extra streams are written uncompressed here; `compress_diff`
+// compresses the whole diff body in one pass with the codec the caller chose.
 fn create_streaming_diff(old_file_path: &Path, new_file_path: &Path, output_path: &Path) -> io::Result<()> {
     let mut old_file = BufReader::new(File::open(old_file_path)?);
     let mut new_file = BufReader::new(File::open(new_file_path)?);
     let mut output_file = BufWriter::new(File::create(output_path)?);
 
-    // Calculate and write original file hash
+    // Calculate and write original file hash (over the file as it sits on
+    // disk, Yaz0 wrapper included, so `apply` can verify it hasn't changed)
     old_file.seek(SeekFrom::Start(0))?;
     let original_file_hash = calculate_file_hash(&mut old_file)?;
     output_file.write_all(&original_file_hash)?;
 
-    // Disassemble files
+    // Read both files fully; the suffix array search needs random access
+    // into the old file and the new file is scanned byte by byte.
     old_file.seek(SeekFrom::Start(0))?;
-    let old_disassembled = streaming_disassemble(&mut old_file)?;
-    let new_disassembled = streaming_disassemble(&mut new_file)?;
-
-    // Normalize disassembled code
-    let old_normalized = normalize(&old_disassembled);
-    let new_normalized = normalize(&new_disassembled);
-
-    // Generate diff and extract address changes
-    let diff = generate_diff(&old_normalized, &new_normalized);
-    let address_changes = extract_address_changes(&old_disassembled, &new_disassembled);
-
-    // Write diff
-    output_file.write_u32::<LittleEndian>(diff.len() as u32)?;
-    for d in diff {
-        output_file.write_u32::<LittleEndian>(d.len() as u32)?;
-        output_file.write_all(d.as_bytes())?;
+    let mut old_raw = Vec::new();
+    old_file.read_to_end(&mut old_raw)?;
+    let mut new_raw = Vec::new();
+    new_file.read_to_end(&mut new_raw)?;
+
+    // Diff the decoded payload, not the Yaz0 container, if either side is
+    // Yaz0-wrapped. Remember both files' wrapped-ness: `apply` needs
+    // `old_was_yaz0` to unwrap the old file before applying the delta, and
+    // `new_was_yaz0` to know whether to re-wrap the reconstructed output.
+    let (old_bytes, old_was_yaz0) = decode_if_yaz0(&old_raw)?;
+    let (new_bytes, new_was_yaz0) = decode_if_yaz0(&new_raw)?;
+    output_file.write_u8(old_was_yaz0 as u8)?;
+    output_file.write_u8(new_was_yaz0 as u8)?;
+
+    let delta = bsdiff::compute_delta(&old_bytes, &new_bytes);
+
+    // Write the control stream
+    let mut control_bytes = Vec::with_capacity(delta.controls.len() * 16);
+    for entry in &delta.controls {
+        control_bytes.write_u32::<LittleEndian>(entry.copy_len)?;
+        control_bytes.write_u32::<LittleEndian>(entry.extra_len)?;
+        control_bytes.write_i64::<LittleEndian>(entry.old_seek)?;
     }
+    output_file.write_u32::<LittleEndian>(delta.controls.len() as u32)?;
+    write_framed_stream(&mut output_file, &control_bytes)?;
 
-    // Write address changes
-    output_file.write_u32::<LittleEndian>(address_changes.len() as u32)?;
-    for change in address_changes {
-        output_file.write_i64::<LittleEndian>(change)?;
-    }
+    // Write the diff and extra payload streams
+    write_framed_stream(&mut output_file, &delta.diff_bytes)?;
+    write_framed_stream(&mut output_file, &delta.extra_bytes)?;
 
     output_file.flush()?;
     Ok(())
 }
 
-// Compress the diff file using gzip
-fn compress_diff(input_path: &Path, output_path: &Path) -> io::Result<()> {
-    let input_file = BufReader::new(File::open(input_path)?);
-    let output_file = BufWriter::new(File::create(output_path)?);
-    let mut encoder = GzEncoder::new(output_file, Compression::default());
-    io::copy(&mut BufReader::new(input_file), &mut encoder)?;
-    encoder.finish()?;
+// Compress the diff file into a self-describing patch container: a magic +
+// version + codec tag + payload length header, followed by the payload
+// compressed with the chosen codec. The length is stored so `open_patch_stream`
+// can bound the decoder to exactly the compressed payload, even for codecs
+// (gzip, bzip2) that don't stop reading at the end of their own stream.
+fn compress_diff(codec: Codec, input_path: &Path, output_path: &Path) -> io::Result<()> {
+    let mut input_file = BufReader::new(File::open(input_path)?);
+    let mut compressed = Vec::new();
+    codec::compress_stream(codec, &mut input_file, &mut compressed)?;
+
+    let mut output_file = BufWriter::new(File::create(output_path)?);
+    output_file.write_all(codec::MAGIC)?;
+    output_file.write_u8(codec::FORMAT_VERSION)?;
+    output_file.write_u8(codec.tag())?;
+    output_file.write_u64::<LittleEndian>(compressed.len() as u64)?;
+    output_file.write_all(&compressed)?;
+    output_file.flush()?;
     Ok(())
 }
 
-// Decompress the diff file from gzip
-fn decompress_diff(input_path: &Path, output_path: &Path) -> io::Result<()> {
-    let input_file = BufReader::new(File::open(input_path)?);
-    let output_file = BufWriter::new(File::create(output_path)?);
-    let mut decoder = GzDecoder::new(input_file);
-    io::copy(&mut decoder, &mut BufWriter::new(output_file))?;
-    Ok(())
+// Open a patch container and return a reader over its decompressed payload.
+// The returned reader decodes on the fly as `apply_streaming_patch` consumes
+// it, rather than expanding the whole patch onto disk first. The payload is
+// wrapped in `Read::take(payload_len)` before it reaches the decoder, so the
+// decoder is never handed bytes past the end of its own compressed payload
+// even if the patch is later embedded inside a larger container.
+fn open_patch_stream(input_path: &Path) -> io::Result<BufReader<Box<dyn Read>>> {
+    let mut input_file = BufReader::new(File::open(input_path)?);
+
+    let mut magic = [0u8; 4];
+    input_file.read_exact(&mut magic)?;
+    if &magic != codec::MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not an oxidiff patch"));
+    }
+    let version = input_file.read_u8()?;
+    if version != codec::FORMAT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported patch format version {}", version),
+        ));
+    }
+    let codec = Codec::from_tag(input_file.read_u8()?)?;
+    let payload_len = input_file.read_u64::<LittleEndian>()?;
+
+    Ok(BufReader::new(codec::decoding_reader(codec, input_file.take(payload_len))?))
 }
 
-// Apply a streaming patch to a file
-fn apply_streaming_patch(file_path: &Path, patch_path: &Path) -> io::Result<()> {
+// Apply a streaming patch produced by `create_streaming_diff` to a file,
+// reading the (already decompressing) `patch` reader incrementally instead
+// of requiring the whole patch to be decompressed to a temp file first.
+fn apply_streaming_patch<R: Read>(file_path: &Path, patch: &mut R) -> io::Result<()> {
     let mut file = File::options().read(true).write(true).open(file_path)?;
-    let mut patch_file = BufReader::new(File::open(patch_path)?);
 
     // Read and verify file hash
     let mut file_hash = [0u8; 32];
-    patch_file.read_exact(&mut file_hash)?;
+    patch.read_exact(&mut file_hash)?;
     let actual_file_hash = calculate_file_hash(&mut file)?;
     if file_hash != actual_file_hash {
         return Err(io::Error::new(io::ErrorKind::InvalidData, "File hash mismatch"));
     }
+    let old_was_yaz0 = patch.read_u8()? != 0;
+    let new_was_yaz0 = patch.read_u8()? != 0;
+
+    // Read the control stream
+    let control_count = patch.read_u32::<LittleEndian>()?;
+    let control_bytes = read_framed_stream(patch)?;
+    let mut control_reader = &control_bytes[..];
+    let mut controls = Vec::with_capacity(control_count as usize);
+    for _ in 0..control_count {
+        controls.push(ControlEntry {
+            copy_len: control_reader.read_u32::<LittleEndian>()?,
+            extra_len: control_reader.read_u32::<LittleEndian>()?,
+            old_seek: control_reader.read_i64::<LittleEndian>()?,
+        });
+    }
+
+    // Read the diff and extra payload streams
+    let diff_bytes = read_framed_stream(patch)?;
+    let extra_bytes = read_framed_stream(patch)?;
+
+    let delta = Delta { controls, diff_bytes, extra_bytes };
+
+    // Reconstruct the new file contents from the old file plus the delta.
+    // The delta was computed against the decoded payload, so unwrap the old
+    // file's Yaz0 container first if it had one, and re-wrap the result.
+    let mut old_raw = Vec::new();
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut old_raw)?;
+    let old_bytes = if old_was_yaz0 { yaz0::decode(&old_raw)? } else { old_raw };
+
+    let new_bytes = bsdiff::apply_delta(&old_bytes, &delta);
+    let final_bytes = if new_was_yaz0 { yaz0::encode(&new_bytes) } else { new_bytes };
+
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(&final_bytes)?;
+
+    Ok(())
+}
+
+// Write an rdiff signature: block size, whole-file hash, block count, then
+// each block's weak checksum and strong hash, compressed as a single stream.
+// The whole-file hash travels with the patch (not just the signature) so
+// `rdiff-apply` can verify the old file matches what the signature was built
+// from before trusting any block index into it.
+fn write_signature(signature: &Signature, output_path: &Path) -> io::Result<()> {
+    let mut output_file = BufWriter::new(File::create(output_path)?);
+    output_file.write_u32::<LittleEndian>(signature.block_size as u32)?;
+    output_file.write_all(&signature.file_hash)?;
+
+    let mut body = Vec::new();
+    body.write_u32::<LittleEndian>(signature.blocks().len() as u32)?;
+    for block in signature.blocks() {
+        body.write_u32::<LittleEndian>(block.weak)?;
+        body.write_all(&block.strong)?;
+    }
+    write_compressed_stream(&mut output_file, Codec::Gzip, &body)?;
+    output_file.flush()
+}
 
-    // Read diff
-    let diff_count = patch_file.read_u32::<LittleEndian>()?;
-    let mut diff = Vec::new();
-    for _ in 0..diff_count {
-        let diff_len = patch_file.read_u32::<LittleEndian>()?;
-        let mut diff_bytes = vec![0u8; diff_len as usize];
-        patch_file.read_exact(&mut diff_bytes)?;
-        diff.push(String::from_utf8(diff_bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?);
+fn read_signature(input_path: &Path) -> io::Result<Signature> {
+    let mut input_file = BufReader::new(File::open(input_path)?);
+    let block_size = input_file.read_u32::<LittleEndian>()? as usize;
+    let mut file_hash = [0u8; 32];
+    input_file.read_exact(&mut file_hash)?;
+
+    let body = read_compressed_stream(&mut input_file, Codec::Gzip)?;
+    let mut reader = &body[..];
+    let block_count = reader.read_u32::<LittleEndian>()?;
+    let mut blocks = Vec::with_capacity(block_count as usize);
+    for _ in 0..block_count {
+        let weak = reader.read_u32::<LittleEndian>()?;
+        let mut strong = [0u8; 32];
+        reader.read_exact(&mut strong)?;
+        blocks.push(rdiff::BlockSignature { weak, strong });
     }
 
-    // Read address changes
-    let change_count = patch_file.read_u32::<LittleEndian>()?;
-    let mut address_changes = Vec::new();
-    for _ in 0..change_count {
-        address_changes.push(patch_file.read_i64::<LittleEndian>()?);
+    Ok(Signature::from_blocks(block_size, file_hash, blocks))
+}
+
+// Write an rdiff command stream: block size, the signature's whole-file hash
+// (so `rdiff-apply` can verify the old file hasn't changed before trusting
+// any block index into it), then a tag byte per command, `Copy` carrying a
+// block index and `Literal` carrying a length-prefixed byte run.
+fn write_rdiff_patch(
+    block_size: usize,
+    file_hash: &[u8; 32],
+    commands: &[RdiffCommand],
+    output_path: &Path,
+) -> io::Result<()> {
+    let mut output_file = BufWriter::new(File::create(output_path)?);
+    output_file.write_u32::<LittleEndian>(block_size as u32)?;
+    output_file.write_all(file_hash)?;
+
+    let mut body = Vec::new();
+    body.write_u32::<LittleEndian>(commands.len() as u32)?;
+    for command in commands {
+        match command {
+            RdiffCommand::Copy(block_index) => {
+                body.write_u8(0)?;
+                body.write_u32::<LittleEndian>(*block_index)?;
+            }
+            RdiffCommand::Literal(bytes) => {
+                body.write_u8(1)?;
+                body.write_u32::<LittleEndian>(bytes.len() as u32)?;
+                body.write_all(bytes)?;
+            }
+        }
     }
+    write_compressed_stream(&mut output_file, Codec::Gzip, &body)?;
+    output_file.flush()
+}
 
-    // Apply patch (simplified version, expand for real use)
-    for (i, change) in address_changes.iter().enumerate() {
-        file.seek(SeekFrom::Start((i * 4) as u64))?;
-        let mut value = file.read_i32::<LittleEndian>()?;
-        value += *change as i32;
-        file.seek(SeekFrom::Start((i * 4) as u64))?;
-        file.write_i32::<LittleEndian>(value)?;
+fn read_rdiff_patch(input_path: &Path) -> io::Result<(usize, [u8; 32], Vec<RdiffCommand>)> {
+    let mut input_file = BufReader::new(File::open(input_path)?);
+    let block_size = input_file.read_u32::<LittleEndian>()? as usize;
+    let mut file_hash = [0u8; 32];
+    input_file.read_exact(&mut file_hash)?;
+
+    let body = read_compressed_stream(&mut input_file, Codec::Gzip)?;
+    let mut reader = &body[..];
+    let command_count = reader.read_u32::<LittleEndian>()?;
+    let mut commands = Vec::with_capacity(command_count as usize);
+    for _ in 0..command_count {
+        match reader.read_u8()? {
+            0 => commands.push(RdiffCommand::Copy(reader.read_u32::<LittleEndian>()?)),
+            1 => {
+                let len = reader.read_u32::<LittleEndian>()?;
+                let mut bytes = vec![0u8; len as usize];
+                reader.read_exact(&mut bytes)?;
+                commands.push(RdiffCommand::Literal(bytes));
+            }
+            tag => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown rdiff command tag {}", tag),
+                ))
+            }
+        }
     }
 
-    Ok(())
+    Ok((block_size, file_hash, commands))
 }
 
 fn main() -> io::Result<()> {
     let args: Vec<String> = std::env::args().collect();
     if args.len() < 3 {
         println!("Usage:");
-        println!("To create a patch: oxidiff create <oldFilePath> <newFilePath>");
+        println!("To create a patch: oxidiff create <oldFilePath> <newFilePath> [--codec gzip|bzip2|zstd|none]");
         println!("To apply a patch: oxidiff apply <fileToUpdatePath> <patchPath>");
+        println!("To build a signature: oxidiff signature <oldFilePath> <signaturePath>");
+        println!("To diff against a signature: oxidiff rdiff-create <signaturePath> <newFilePath> <patchPath>");
+        println!("To apply an rdiff patch: oxidiff rdiff-apply <oldFilePath> <patchPath> <outputFilePath>");
         return Ok(());
     }
 
     match args[1].as_str() {
         "create" => {
-            if args.len() != 4 {
-                println!("Usage: oxidiff create <oldFilePath> <newFilePath>");
+            if args.len() != 4 && args.len() != 6 {
+                println!("Usage: oxidiff create <oldFilePath> <newFilePath> [--codec gzip|bzip2|zstd|none]");
                 return Ok(());
             }
             let old_file_path = Path::new(&args[2]);
             let new_file_path = Path::new(&args[3]);
 
+            let codec = if args.len() == 6 {
+                if args[4] != "--codec" {
+                    println!("Usage: oxidiff create <oldFilePath> <newFilePath> [--codec gzip|bzip2|zstd|none]");
+                    return Ok(());
+                }
+                match Codec::from_flag(&args[5]) {
+                    Some(codec) => codec,
+                    None => {
+                        println!("Unknown codec '{}'. Expected gzip, bzip2, zstd or none.", args[5]);
+                        return Ok(());
+                    }
+                }
+            } else {
+                Codec::Gzip
+            };
+
             // Create uncompressed diff
             let uncompressed_diff_path = Path::new("uncompressed_diff.bin");
             create_streaming_diff(old_file_path, new_file_path, uncompressed_diff_path)?;
@@ -232,7 +395,7 @@ fn main() -> io::Result<()> {
 
             // Compress the diff
             let compressed_diff_path = Path::new("compressed_diff.bin");
-            compress_diff(uncompressed_diff_path, compressed_diff_path)?;
+            compress_diff(codec, uncompressed_diff_path, compressed_diff_path)?;
             println!("Compressed diff saved to: {:?}", compressed_diff_path);
 
             // Clean up uncompressed diff
@@ -246,20 +409,71 @@ fn main() -> io::Result<()> {
             let file_to_update_path = Path::new(&args[2]);
             let compressed_patch_path = Path::new(&args[3]);
 
-            // Decompress the patch
-            let uncompressed_patch_path = Path::new("temp_uncompressed_patch.bin");
-            decompress_diff(compressed_patch_path, uncompressed_patch_path)?;
+            // Stream the patch straight from the compressed container; no
+            // intermediate decompressed file is written to disk.
+            let mut patch_stream = open_patch_stream(compressed_patch_path)?;
 
-            // Apply the patch
-            match apply_streaming_patch(file_to_update_path, uncompressed_patch_path) {
+            match apply_streaming_patch(file_to_update_path, &mut patch_stream) {
                 Ok(_) => println!("Patch applied successfully"),
                 Err(e) => println!("Failed to apply patch: {}", e),
             }
+        }
+        "signature" => {
+            if args.len() != 4 {
+                println!("Usage: oxidiff signature <oldFilePath> <signaturePath>");
+                return Ok(());
+            }
+            let old_file_path = Path::new(&args[2]);
+            let signature_path = Path::new(&args[3]);
 
-            // Clean up temporary uncompressed patch
-            std::fs::remove_file(uncompressed_patch_path)?;
+            let mut old_bytes = Vec::new();
+            File::open(old_file_path)?.read_to_end(&mut old_bytes)?;
+            let signature = rdiff::build_signature(&old_bytes);
+            write_signature(&signature, signature_path)?;
+            println!("Signature saved to: {:?}", signature_path);
+        }
+        "rdiff-create" => {
+            if args.len() != 5 {
+                println!("Usage: oxidiff rdiff-create <signaturePath> <newFilePath> <patchPath>");
+                return Ok(());
+            }
+            let signature_path = Path::new(&args[2]);
+            let new_file_path = Path::new(&args[3]);
+            let patch_path = Path::new(&args[4]);
+
+            let signature = read_signature(signature_path)?;
+            let mut new_bytes = Vec::new();
+            File::open(new_file_path)?.read_to_end(&mut new_bytes)?;
+            let commands = rdiff::compute_delta(&signature, &new_bytes);
+            write_rdiff_patch(signature.block_size, &signature.file_hash, &commands, patch_path)?;
+            println!("Patch saved to: {:?}", patch_path);
+        }
+        "rdiff-apply" => {
+            if args.len() != 5 {
+                println!("Usage: oxidiff rdiff-apply <oldFilePath> <patchPath> <outputFilePath>");
+                return Ok(());
+            }
+            let old_file_path = Path::new(&args[2]);
+            let patch_path = Path::new(&args[3]);
+            let output_path = Path::new(&args[4]);
+
+            let mut old_bytes = Vec::new();
+            File::open(old_file_path)?.read_to_end(&mut old_bytes)?;
+            let (block_size, file_hash, commands) = read_rdiff_patch(patch_path)?;
+            let actual_file_hash = calculate_file_hash(&mut &old_bytes[..])?;
+            if file_hash != actual_file_hash {
+                println!("Failed to apply patch: old file hash mismatch");
+                return Ok(());
+            }
+            match rdiff::apply_delta(&old_bytes, &commands, block_size) {
+                Ok(new_bytes) => {
+                    File::create(output_path)?.write_all(&new_bytes)?;
+                    println!("Patch applied successfully");
+                }
+                Err(e) => println!("Failed to apply patch: {}", e),
+            }
         }
-        _ => println!("Invalid command. Use 'create' or 'apply'."),
+        _ => println!("Invalid command. Use 'create', 'apply', 'signature', 'rdiff-create' or 'rdiff-apply'."),
     }
 
     Ok(())
@@ -0,0 +1,301 @@
+// An rsync/rdiff-style signature + delta mode.
+//
+// Unlike the bsdiff engine, this mode never needs the old and new files on
+// the same host at the same time: the old file is summarized into a small
+// signature (a weak rolling checksum plus a strong hash per block), and the
+// new file is diffed against that signature alone. A block-sized window is
+// rolled byte by byte over the new file; a weak-checksum hit that is
+// confirmed by the strong hash becomes a `Copy` of that old block, and
+// everything else is emitted as `Literal` bytes.
+
+use std::collections::HashMap;
+use std::io;
+
+use crate::calculate_file_hash;
+
+/// Block size used when building a signature, in bytes.
+pub const BLOCK_SIZE: usize = 2048;
+
+const ADLER_MODULUS: u32 = 65521;
+
+/// The rolling weak checksum and strong hash for a single block of the old
+/// file.
+#[derive(Clone, Debug)]
+pub struct BlockSignature {
+    pub weak: u32,
+    pub strong: [u8; 32],
+}
+
+/// A compact summary of the old file: a whole-file hash (so a delta can be
+/// checked against the old file it was actually computed from) plus one
+/// signature per fixed-size block.
+#[derive(Clone, Debug)]
+pub struct Signature {
+    pub block_size: usize,
+    pub file_hash: [u8; 32],
+    blocks: Vec<BlockSignature>,
+    // Weak checksum -> indices of blocks sharing that checksum.
+    by_weak: HashMap<u32, Vec<usize>>,
+}
+
+impl Signature {
+    /// Rebuild a `Signature` (including the weak-checksum index) from its
+    /// serialized blocks.
+    pub fn from_blocks(block_size: usize, file_hash: [u8; 32], blocks: Vec<BlockSignature>) -> Self {
+        let mut by_weak: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (index, block) in blocks.iter().enumerate() {
+            by_weak.entry(block.weak).or_default().push(index);
+        }
+        Self { block_size, file_hash, blocks, by_weak }
+    }
+
+    pub fn blocks(&self) -> &[BlockSignature] {
+        &self.blocks
+    }
+}
+
+/// One entry in the delta command stream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RdiffCommand {
+    Copy(u32),
+    Literal(Vec<u8>),
+}
+
+fn strong_hash(block: &[u8]) -> [u8; 32] {
+    // Reuse the same hasher `calculate_file_hash` uses, just over a block
+    // instead of a whole file.
+    calculate_file_hash(&mut &block[..]).expect("hashing an in-memory slice cannot fail")
+}
+
+/// Adler-32-style weak checksum: `a = sum(bytes) mod M`,
+/// `b = sum((len-i)*byte) mod M`, combined as `a | (b << 16)`.
+fn weak_checksum(block: &[u8]) -> u32 {
+    let len = block.len();
+    let mut a: u32 = 0;
+    let mut b: u32 = 0;
+    for (i, &byte) in block.iter().enumerate() {
+        a = (a + byte as u32) % ADLER_MODULUS;
+        b = (b + (len - i) as u32 * byte as u32) % ADLER_MODULUS;
+    }
+    a | (b << 16)
+}
+
+/// Incrementally rolls the weak checksum across a fixed-size window as it
+/// slides forward one byte at a time.
+struct RollingChecksum {
+    a: u32,
+    b: u32,
+    len: u32,
+}
+
+impl RollingChecksum {
+    fn new(block: &[u8]) -> Self {
+        let len = block.len() as u32;
+        let mut a: u32 = 0;
+        let mut b: u32 = 0;
+        for (i, &byte) in block.iter().enumerate() {
+            a = (a + byte as u32) % ADLER_MODULUS;
+            b = (b + (block.len() - i) as u32 * byte as u32) % ADLER_MODULUS;
+        }
+        Self { a, b, len }
+    }
+
+    fn value(&self) -> u32 {
+        self.a | (self.b << 16)
+    }
+
+    // Roll the window forward by one byte: `out_byte` leaves the window,
+    // `in_byte` enters it.
+    fn roll(&mut self, out_byte: u8, in_byte: u8) {
+        self.a = (self.a + ADLER_MODULUS - (out_byte as u32 % ADLER_MODULUS)) % ADLER_MODULUS;
+        self.a = (self.a + in_byte as u32) % ADLER_MODULUS;
+        self.b = (self.b + ADLER_MODULUS
+            - (self.len * (out_byte as u32 % ADLER_MODULUS)) % ADLER_MODULUS)
+            % ADLER_MODULUS;
+        self.b = (self.b + self.a) % ADLER_MODULUS;
+    }
+}
+
+/// Split `old` into fixed-size blocks and compute a weak + strong signature
+/// for each.
+pub fn build_signature(old: &[u8]) -> Signature {
+    let mut blocks = Vec::new();
+    let mut by_weak: HashMap<u32, Vec<usize>> = HashMap::new();
+
+    for (index, chunk) in old.chunks(BLOCK_SIZE).enumerate() {
+        let weak = weak_checksum(chunk);
+        let strong = strong_hash(chunk);
+        by_weak.entry(weak).or_default().push(index);
+        blocks.push(BlockSignature { weak, strong });
+    }
+
+    let file_hash = calculate_file_hash(&mut &old[..]).expect("hashing an in-memory slice cannot fail");
+    Signature { block_size: BLOCK_SIZE, file_hash, blocks, by_weak }
+}
+
+/// Diff `new` against `signature`, without requiring the old file's bytes.
+pub fn compute_delta(signature: &Signature, new: &[u8]) -> Vec<RdiffCommand> {
+    let block_size = signature.block_size;
+    let mut commands = Vec::new();
+    let mut literal_run = Vec::new();
+
+    if new.is_empty() {
+        return commands;
+    }
+
+    let mut pos = 0usize;
+    let window_len = block_size.min(new.len());
+    let mut rolling = RollingChecksum::new(&new[pos..pos + window_len]);
+
+    while pos < new.len() {
+        let window_len = block_size.min(new.len() - pos);
+        let window = &new[pos..pos + window_len];
+
+        let mut matched_block = None;
+        if window_len == block_size {
+            if let Some(candidates) = signature.by_weak.get(&rolling.value()) {
+                let strong = strong_hash(window);
+                for &candidate in candidates {
+                    if signature.blocks[candidate].strong == strong {
+                        matched_block = Some(candidate);
+                        break;
+                    }
+                }
+            }
+        }
+
+        if let Some(block_index) = matched_block {
+            if !literal_run.is_empty() {
+                commands.push(RdiffCommand::Literal(std::mem::take(&mut literal_run)));
+            }
+            commands.push(RdiffCommand::Copy(block_index as u32));
+            pos += block_size;
+            if pos < new.len() {
+                let next_len = block_size.min(new.len() - pos);
+                rolling = RollingChecksum::new(&new[pos..pos + next_len]);
+            }
+        } else {
+            literal_run.push(window[0]);
+            let out_byte = window[0];
+            pos += 1;
+            if pos + window_len <= new.len() {
+                let in_byte = new[pos + window_len - 1];
+                rolling.roll(out_byte, in_byte);
+            } else if pos < new.len() {
+                let next_len = new.len() - pos;
+                rolling = RollingChecksum::new(&new[pos..pos + next_len]);
+            }
+        }
+    }
+
+    if !literal_run.is_empty() {
+        commands.push(RdiffCommand::Literal(literal_run));
+    }
+
+    commands
+}
+
+/// Reconstruct the new file from the old file's blocks plus literal runs.
+///
+/// `commands` were computed against a specific old file's signature; if
+/// `old` isn't that same file (or is truncated), a `Copy` can reference a
+/// block index past the end of `old`. Rather than let that panic on an
+/// out-of-range slice, it's reported as an error so callers can verify the
+/// old file first instead of relying on this to catch it.
+pub fn apply_delta(old: &[u8], commands: &[RdiffCommand], block_size: usize) -> io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    for command in commands {
+        match command {
+            RdiffCommand::Copy(block_index) => {
+                let start = *block_index as usize * block_size;
+                if start >= old.len() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("rdiff block index {} is out of range for the old file", block_index),
+                    ));
+                }
+                let end = (start + block_size).min(old.len());
+                output.extend_from_slice(&old[start..end]);
+            }
+            RdiffCommand::Literal(bytes) => output.extend_from_slice(bytes),
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(old: &[u8], new: &[u8]) {
+        let signature = build_signature(old);
+        let commands = compute_delta(&signature, new);
+        let reconstructed = apply_delta(old, &commands, signature.block_size).unwrap();
+        assert_eq!(reconstructed, new);
+    }
+
+    #[test]
+    fn round_trip_identical_files() {
+        let data = vec![7u8; BLOCK_SIZE * 3];
+        round_trip(&data, &data);
+    }
+
+    #[test]
+    fn round_trip_insertion() {
+        let mut old = vec![1u8; BLOCK_SIZE * 2];
+        old.extend(vec![2u8; BLOCK_SIZE]);
+        let mut new = old[..BLOCK_SIZE].to_vec();
+        new.extend(b"inserted, not aligned to a block boundary");
+        new.extend(&old[BLOCK_SIZE..]);
+        round_trip(&old, &new);
+    }
+
+    #[test]
+    fn round_trip_deletion() {
+        let mut old = vec![3u8; BLOCK_SIZE];
+        old.extend(vec![4u8; BLOCK_SIZE]);
+        old.extend(vec![5u8; BLOCK_SIZE]);
+        let mut new = old[..BLOCK_SIZE].to_vec();
+        new.extend(&old[BLOCK_SIZE * 2..]);
+        round_trip(&old, &new);
+    }
+
+    #[test]
+    fn round_trip_empty_old_file() {
+        round_trip(b"", b"freshly created content");
+    }
+
+    #[test]
+    fn round_trip_empty_new_file() {
+        round_trip(&vec![9u8; BLOCK_SIZE], b"");
+    }
+
+    #[test]
+    fn round_trip_repeated_bytes() {
+        let old = vec![0u8; BLOCK_SIZE * 4];
+        let mut new = vec![0u8; BLOCK_SIZE * 3];
+        new.extend(vec![1u8; BLOCK_SIZE]);
+        round_trip(&old, &new);
+    }
+
+    #[test]
+    fn rolling_checksum_matches_direct_computation() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(BLOCK_SIZE * 2).collect();
+        let window_len = BLOCK_SIZE / 2;
+        let mut rolling = RollingChecksum::new(&data[0..window_len]);
+        assert_eq!(rolling.value(), weak_checksum(&data[0..window_len]));
+
+        for pos in 1..=(data.len() - window_len) {
+            rolling.roll(data[pos - 1], data[pos + window_len - 1]);
+            let expected = weak_checksum(&data[pos..pos + window_len]);
+            assert_eq!(rolling.value(), expected, "mismatch at window starting {}", pos);
+        }
+    }
+
+    #[test]
+    fn apply_delta_rejects_out_of_range_block_index() {
+        let commands = vec![RdiffCommand::Copy(5)];
+        let result = apply_delta(&vec![0u8; BLOCK_SIZE], &commands, BLOCK_SIZE);
+        assert!(result.is_err());
+    }
+}